@@ -11,6 +11,14 @@ use worm_std::process::Command;
 fn main() {
     worm_std::print_banner();
 
+    // Installed first, before any threads or child processes exist, per
+    // `install_network_seccomp`'s docs.
+    match worm_std::sandbox::install_network_seccomp() {
+        Ok(()) => println!("✓ seccomp network filter installed"),
+        Err(e) => println!("✗ seccomp install failed: {}", e),
+    }
+    println!();
+
     println!("Network Security Demonstration");
     println!("==============================");
     println!();
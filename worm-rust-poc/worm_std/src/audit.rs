@@ -0,0 +1,249 @@
+//! Security Audit Log
+//!
+//! The crate used to report security decisions with one-off `eprintln!`
+//! calls, leaving no machine-readable trail of what the sandbox stopped.
+//! This module records a structured event for each blocked command,
+//! detected URL pattern and denied file path as newline-delimited JSON,
+//! so operators can feed it into log tooling instead of scraping console
+//! output. Environment variables denied by `process::Command::env` are
+//! not recorded, and in warn mode only the first disallowed host of a
+//! command is recorded as a warning.
+//!
+//! Seccomp rejections aren't included: `crate::sandbox` installs its
+//! filter with `SECCOMP_RET_ERRNO`, which gives the kernel no hook back
+//! into this process to record anything - the syscall just fails. Logging
+//! that would need `SECCOMP_RET_TRAP`/`SECCOMP_RET_LOG` and a signal
+//! handler instead, which is a bigger change than this module.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::sync::{Mutex, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What kind of decision an [`AuditEvent`] records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventType {
+    /// `process::Command::new` refused to run a program.
+    BlockedCommand,
+    /// A URL pattern was found in a command's arguments or environment.
+    UrlPattern,
+    /// A filesystem path was denied by the active policy.
+    PathDenied,
+}
+
+impl fmt::Display for EventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            EventType::BlockedCommand => "blocked_command",
+            EventType::UrlPattern => "url_pattern",
+            EventType::PathDenied => "path_denied",
+        };
+        f.write_str(s)
+    }
+}
+
+/// What the sandbox did about an [`AuditEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Blocked,
+    Warned,
+    Allowed,
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Action::Blocked => "blocked",
+            Action::Warned => "warned",
+            Action::Allowed => "allowed",
+        };
+        f.write_str(s)
+    }
+}
+
+/// A single security decision, recorded in newline-delimited JSON.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    /// Unix timestamp, in seconds, when the decision was made.
+    pub timestamp: u64,
+    pub event_type: EventType,
+    /// The program involved or, for a denied path, the operation that was
+    /// refused (e.g. `read`); the path itself is in `detail`.
+    pub program: String,
+    /// A human-readable description of what triggered the event.
+    pub detail: String,
+    pub action: Action,
+}
+
+impl AuditEvent {
+    fn new(event_type: EventType, program: impl Into<String>, detail: impl Into<String>, action: Action) -> Self {
+        AuditEvent {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            event_type,
+            program: program.into(),
+            detail: detail.into(),
+            action,
+        }
+    }
+
+    /// Renders this event as one line of newline-delimited JSON (no
+    /// trailing newline).
+    pub fn to_json(&self) -> String {
+        format!(
+            "{{\"timestamp\":{},\"event_type\":\"{}\",\"program\":{},\"detail\":{},\"action\":\"{}\"}}",
+            self.timestamp,
+            self.event_type,
+            json_string(&self.program),
+            json_string(&self.detail),
+            self.action,
+        )
+    }
+}
+
+/// Escapes `s` as a JSON string literal, including the surrounding quotes.
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Where audit events are written.
+#[derive(Default)]
+pub enum Sink {
+    #[default]
+    Stderr,
+    File(std::fs::File),
+    Custom(Box<dyn Write + Send>),
+}
+
+impl Sink {
+    /// A sink that opens `path` for appending, creating it if necessary.
+    pub fn file(path: impl AsRef<std::path::Path>) -> io::Result<Sink> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Sink::File(file))
+    }
+
+    /// A sink that writes to any `Write` implementation, e.g. a socket-free
+    /// in-memory buffer in tests.
+    pub fn custom(writer: impl Write + Send + 'static) -> Sink {
+        Sink::Custom(Box::new(writer))
+    }
+
+    fn write_line(&mut self, line: &str) -> io::Result<()> {
+        match self {
+            Sink::Stderr => writeln!(io::stderr(), "{}", line),
+            Sink::File(f) => writeln!(f, "{}", line),
+            Sink::Custom(w) => writeln!(w, "{}", line),
+        }
+    }
+}
+
+static SINK: OnceLock<Mutex<Sink>> = OnceLock::new();
+
+fn sink() -> &'static Mutex<Sink> {
+    SINK.get_or_init(|| Mutex::new(Sink::default()))
+}
+
+/// Installs the sink audit events are written to. Defaults to stderr if
+/// never called.
+pub fn set_sink(new_sink: Sink) {
+    match SINK.get() {
+        Some(lock) => *lock.lock().unwrap() = new_sink,
+        None => {
+            let _ = SINK.set(Mutex::new(new_sink));
+        }
+    }
+}
+
+fn record(event: AuditEvent) {
+    let line = event.to_json();
+    let _ = sink().lock().unwrap().write_line(&line);
+}
+
+/// Records that a program was refused execution.
+pub fn log_blocked_command(program: &str, detail: &str) {
+    record(AuditEvent::new(EventType::BlockedCommand, program, detail, Action::Blocked));
+}
+
+/// Records that a URL pattern was found. `action` is `Action::Allowed`
+/// when the active policy permits the host, and otherwise
+/// `Action::Blocked` when the policy treats detection as a hard block, or
+/// `Action::Warned` when it's advisory.
+pub fn log_url_pattern(program: &str, url: &str, action: Action) {
+    record(AuditEvent::new(EventType::UrlPattern, program, url, action));
+}
+
+/// Records that a filesystem path was denied by the active policy.
+pub fn log_path_denied(operation: &str, path: &std::path::Path) {
+    record(AuditEvent::new(
+        EventType::PathDenied,
+        operation,
+        path.display().to_string(),
+        Action::Blocked,
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex as StdMutex};
+
+    #[derive(Clone, Default)]
+    struct SharedBuf(Arc<StdMutex<Vec<u8>>>);
+
+    impl Write for SharedBuf {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn event_serializes_to_one_json_line() {
+        let event = AuditEvent::new(EventType::BlockedCommand, "curl", "blocked by policy", Action::Blocked);
+        let json = event.to_json();
+        assert!(json.starts_with('{') && json.ends_with('}'));
+        assert!(json.contains("\"event_type\":\"blocked_command\""));
+        assert!(json.contains("\"program\":\"curl\""));
+        assert!(json.contains("\"action\":\"blocked\""));
+        assert!(!json.contains('\n'));
+    }
+
+    #[test]
+    fn json_string_escapes_quotes_and_control_chars() {
+        assert_eq!(json_string("a\"b\\c\n"), "\"a\\\"b\\\\c\\n\"");
+    }
+
+    #[test]
+    fn custom_sink_receives_events() {
+        let buf = SharedBuf::default();
+        set_sink(Sink::custom(buf.clone()));
+
+        log_blocked_command("wget", "network command");
+
+        let written = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        assert!(written.contains("\"program\":\"wget\""));
+
+        set_sink(Sink::default());
+    }
+}
@@ -0,0 +1,492 @@
+//! Policy Config Files
+//!
+//! Hardcoding a [`crate::policy::Policy`] in source doesn't scale across
+//! deployments, so this module loads one from a small `cfg(...)`-style
+//! expression language (the grammar Cargo uses for `cfg(unix)`,
+//! `cfg(not(windows))`, ...), e.g.:
+//!
+//! ```text
+//! all(run("ls"), not(net))
+//! any(read("/tmp"), write("/var/log"))
+//! ```
+//!
+//! Each predicate (`run`, `read`, `write`, `env`, `net`) names a
+//! capability; `all`/`any`/`not` combine them. The expression is
+//! evaluated per access attempt against the specific thing being
+//! requested - `run("ls")` is true exactly when the program being
+//! checked is `ls`, `read("/tmp")` is true when the path being checked
+//! falls under `/tmp`, and so on - so the same expression that *reads*
+//! like a static declaration doubles as the access check itself.
+
+use crate::policy::Policy;
+use std::fmt;
+use std::path::Path;
+
+// ============================================================================
+// TOKENIZER
+// ============================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum TokenKind {
+    Ident(String),
+    Str(String),
+    LParen,
+    RParen,
+    Comma,
+    Eof,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    pos: usize,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i] as char;
+        match c {
+            c if c.is_whitespace() => i += 1,
+            '(' => {
+                tokens.push(Token { kind: TokenKind::LParen, pos: i });
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token { kind: TokenKind::RParen, pos: i });
+                i += 1;
+            }
+            ',' => {
+                tokens.push(Token { kind: TokenKind::Comma, pos: i });
+                i += 1;
+            }
+            '"' => {
+                let start = i;
+                i += 1;
+                let mut s = String::new();
+                loop {
+                    if i >= bytes.len() {
+                        return Err(ParseError::new(start, "unterminated string literal"));
+                    }
+                    let c = bytes[i] as char;
+                    if c == '"' {
+                        i += 1;
+                        break;
+                    }
+                    if c == '\\' && i + 1 < bytes.len() {
+                        s.push(bytes[i + 1] as char);
+                        i += 2;
+                    } else {
+                        s.push(c);
+                        i += 1;
+                    }
+                }
+                tokens.push(Token { kind: TokenKind::Str(s), pos: start });
+            }
+            c if c.is_alphanumeric() || c == '_' || c == '-' => {
+                let start = i;
+                while i < bytes.len() {
+                    let c = bytes[i] as char;
+                    if c.is_alphanumeric() || c == '_' || c == '-' {
+                        i += 1;
+                    } else {
+                        break;
+                    }
+                }
+                let ident = input[start..i].to_string();
+                tokens.push(Token { kind: TokenKind::Ident(ident), pos: start });
+            }
+            other => {
+                return Err(ParseError::new(i, format!("unexpected character '{}'", other)));
+            }
+        }
+    }
+
+    tokens.push(Token { kind: TokenKind::Eof, pos: bytes.len() });
+    Ok(tokens)
+}
+
+// ============================================================================
+// AST
+// ============================================================================
+
+/// A policy capability predicate: the leaves of a [`CfgExpr`] tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Predicate {
+    /// `run("program")` - matches when the program being checked equals
+    /// this name or base name.
+    Run(String),
+    /// `read("/prefix")` - matches when the path being checked falls
+    /// under this prefix.
+    Read(String),
+    /// `write("/prefix")` - matches when the path being checked falls
+    /// under this prefix.
+    Write(String),
+    /// `env("VAR")` - matches when the environment variable being
+    /// checked equals this name.
+    Env(String),
+    /// `net` - matches a network-access check.
+    Net,
+}
+
+/// A parsed policy expression, in the spirit of Cargo's `cfg(...)`
+/// grammar: `all`/`any`/`not` combinators over [`Predicate`] leaves.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CfgExpr {
+    All(Vec<CfgExpr>),
+    Any(Vec<CfgExpr>),
+    Not(Box<CfgExpr>),
+    Predicate(Predicate),
+}
+
+/// What's being checked when [`CfgExpr`] is evaluated against a
+/// specific access attempt.
+#[derive(Debug, Clone, Copy)]
+pub enum Query<'a> {
+    Run(&'a str),
+    Read(&'a Path),
+    Write(&'a Path),
+    Env(&'a str),
+    Net,
+}
+
+impl CfgExpr {
+    /// Evaluates this expression against a specific access attempt.
+    ///
+    /// A predicate only ever speaks to queries of its own category
+    /// (`run`/`read`/`write`/`env`/`net`) - e.g. `run("ls")` has nothing to
+    /// say about a `read` query. `evaluate_opt` returns `None` for that
+    /// "doesn't apply" case instead of collapsing it to `false`, so `all`/
+    /// `any` can tell "this branch voted no" apart from "this branch has
+    /// no opinion". The distinction matters: `all(run("ls"), not(net))`
+    /// should restrict only `run` and `net` - it says nothing about
+    /// `read`/`write`/`env`, which should keep the crate's usual
+    /// permissive default rather than being silently denied because every
+    /// leaf in the `all()` evaluated to `false` for them.
+    ///
+    /// A category the whole expression never mentions (`evaluate_opt`
+    /// returns `None`) defaults to permitted here, matching the
+    /// permissive-unless-restricted behavior of `Policy`'s other fields -
+    /// except `net`, which defaults to denied, matching `Policy`'s
+    /// deny-network default.
+    pub fn evaluate(&self, query: &Query<'_>) -> bool {
+        self.evaluate_opt(query).unwrap_or(!matches!(query, Query::Net))
+    }
+
+    /// `Some(bool)` if this expression (or a sub-expression of it) has an
+    /// opinion on `query`'s category, `None` if it's entirely silent on it.
+    fn evaluate_opt(&self, query: &Query<'_>) -> Option<bool> {
+        match self {
+            CfgExpr::All(exprs) => {
+                let votes: Vec<Option<bool>> = exprs.iter().map(|e| e.evaluate_opt(query)).collect();
+                if votes.contains(&Some(false)) {
+                    Some(false)
+                } else if votes.iter().any(Option::is_some) {
+                    Some(true)
+                } else {
+                    None
+                }
+            }
+            CfgExpr::Any(exprs) => {
+                let votes: Vec<Option<bool>> = exprs.iter().map(|e| e.evaluate_opt(query)).collect();
+                if votes.contains(&Some(true)) {
+                    Some(true)
+                } else if votes.iter().any(Option::is_some) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }
+            CfgExpr::Not(inner) => inner.evaluate_opt(query).map(|b| !b),
+            CfgExpr::Predicate(p) => p.evaluate_opt(query),
+        }
+    }
+}
+
+impl Predicate {
+    /// `Some(bool)` if `self` and `query` are the same category (the
+    /// predicate has an opinion), `None` otherwise (a different category -
+    /// this predicate doesn't apply to `query` at all).
+    fn evaluate_opt(&self, query: &Query<'_>) -> Option<bool> {
+        match (self, query) {
+            (Predicate::Run(name), Query::Run(program)) => Some(program_matches(name, program)),
+            (Predicate::Read(prefix), Query::Read(path)) => Some(path.starts_with(prefix)),
+            (Predicate::Write(prefix), Query::Write(path)) => Some(path.starts_with(prefix)),
+            (Predicate::Env(var), Query::Env(name)) => Some(var == name),
+            (Predicate::Net, Query::Net) => Some(true),
+            _ => None,
+        }
+    }
+}
+
+fn program_matches(pattern: &str, program: &str) -> bool {
+    if pattern == program {
+        return true;
+    }
+    Path::new(program).file_name().and_then(|s| s.to_str()) == Some(pattern)
+}
+
+// ============================================================================
+// PARSER
+// ============================================================================
+
+/// A policy-expression syntax error, with the byte position it occurred at.
+#[derive(Debug, Clone)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    fn new(position: usize, message: impl Into<String>) -> Self {
+        ParseError {
+            position,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "policy parse error at byte {}: {}", self.position, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    fn peek_pos(&self) -> usize {
+        self.tokens[self.pos].pos
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn expect(&mut self, kind: &TokenKind) -> Result<(), ParseError> {
+        if self.peek() == kind {
+            self.advance();
+            Ok(())
+        } else {
+            Err(ParseError::new(
+                self.peek_pos(),
+                format!("expected {:?}, found {:?}", kind, self.peek()),
+            ))
+        }
+    }
+
+    fn expect_string(&mut self) -> Result<String, ParseError> {
+        let pos = self.peek_pos();
+        match self.advance().kind {
+            TokenKind::Str(s) => Ok(s),
+            other => Err(ParseError::new(pos, format!("expected a string literal, found {:?}", other))),
+        }
+    }
+
+    fn parse_expr(&mut self) -> Result<CfgExpr, ParseError> {
+        let ident = match self.peek().clone() {
+            TokenKind::Ident(name) => name,
+            other => return Err(ParseError::new(self.peek_pos(), format!("expected an identifier, found {:?}", other))),
+        };
+        let start_pos = self.peek_pos();
+        self.advance();
+
+        match ident.as_str() {
+            "all" => Ok(CfgExpr::All(self.parse_expr_list()?)),
+            "any" => Ok(CfgExpr::Any(self.parse_expr_list()?)),
+            "not" => {
+                self.expect(&TokenKind::LParen)?;
+                let inner = self.parse_expr()?;
+                self.expect(&TokenKind::RParen)?;
+                Ok(CfgExpr::Not(Box::new(inner)))
+            }
+            "net" => Ok(CfgExpr::Predicate(Predicate::Net)),
+            "run" | "read" | "write" | "env" => {
+                self.expect(&TokenKind::LParen)?;
+                let value = self.expect_string()?;
+                self.expect(&TokenKind::RParen)?;
+                let predicate = match ident.as_str() {
+                    "run" => Predicate::Run(value),
+                    "read" => Predicate::Read(value),
+                    "write" => Predicate::Write(value),
+                    "env" => Predicate::Env(value),
+                    _ => unreachable!(),
+                };
+                Ok(CfgExpr::Predicate(predicate))
+            }
+            other => Err(ParseError::new(start_pos, format!("unknown predicate or combinator '{}'", other))),
+        }
+    }
+
+    fn parse_expr_list(&mut self) -> Result<Vec<CfgExpr>, ParseError> {
+        self.expect(&TokenKind::LParen)?;
+        let mut exprs = Vec::new();
+        if self.peek() != &TokenKind::RParen {
+            exprs.push(self.parse_expr()?);
+            while self.peek() == &TokenKind::Comma {
+                self.advance();
+                exprs.push(self.parse_expr()?);
+            }
+        }
+        self.expect(&TokenKind::RParen)?;
+        Ok(exprs)
+    }
+}
+
+/// Parses a single policy expression, e.g. `all(run("ls"), not(net))`.
+pub fn parse(input: &str) -> Result<CfgExpr, ParseError> {
+    let tokens = tokenize(input)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let expr = parser.parse_expr()?;
+    if parser.peek() != &TokenKind::Eof {
+        return Err(ParseError::new(parser.peek_pos(), format!("unexpected trailing input: {:?}", parser.peek())));
+    }
+    Ok(expr)
+}
+
+// ============================================================================
+// LOADING A POLICY FROM A FILE
+// ============================================================================
+
+/// Either form of failure when loading a `worm.policy` file.
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(std::io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "failed to read policy file: {}", e),
+            ConfigError::Parse(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<std::io::Error> for ConfigError {
+    fn from(e: std::io::Error) -> Self {
+        ConfigError::Io(e)
+    }
+}
+
+impl From<ParseError> for ConfigError {
+    fn from(e: ParseError) -> Self {
+        ConfigError::Parse(e)
+    }
+}
+
+/// Loads a [`Policy`] from a `cfg(...)`-style expression file (see the
+/// module docs for the grammar). Reads with plain `std::fs`, since this
+/// typically runs before any policy/sandbox has been installed.
+pub fn load_policy_file<P: AsRef<Path>>(path: P) -> Result<Policy, ConfigError> {
+    let contents = std::fs::read_to_string(path)?;
+    let expr = parse(contents.trim())?;
+    Ok(Policy::from_expr(expr))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_all_not_net() {
+        let expr = parse(r#"all(run("ls"), not(net))"#).unwrap();
+        assert_eq!(
+            expr,
+            CfgExpr::All(vec![
+                CfgExpr::Predicate(Predicate::Run("ls".into())),
+                CfgExpr::Not(Box::new(CfgExpr::Predicate(Predicate::Net))),
+            ])
+        );
+    }
+
+    #[test]
+    fn parses_any_read_write() {
+        let expr = parse(r#"any(read("/tmp"), write("/var/log"))"#).unwrap();
+        assert_eq!(
+            expr,
+            CfgExpr::Any(vec![
+                CfgExpr::Predicate(Predicate::Read("/tmp".into())),
+                CfgExpr::Predicate(Predicate::Write("/var/log".into())),
+            ])
+        );
+    }
+
+    #[test]
+    fn reports_position_of_unterminated_string() {
+        let err = parse(r#"run("ls)"#).unwrap_err();
+        assert!(err.message.contains("unterminated"));
+        assert_eq!(err.position, 4);
+    }
+
+    #[test]
+    fn reports_position_of_missing_paren() {
+        let err = parse(r#"all(run("ls")"#).unwrap_err();
+        assert!(err.message.contains("expected") || err.message.contains("unexpected"));
+        assert_eq!(err.position, 13);
+    }
+
+    #[test]
+    fn reports_unknown_predicate() {
+        let err = parse(r#"maybe(net)"#).unwrap_err();
+        assert!(err.message.contains("unknown predicate"));
+    }
+
+    #[test]
+    fn evaluate_all_run_not_net() {
+        let expr = parse(r#"all(run("ls"), not(net))"#).unwrap();
+        assert!(expr.evaluate(&Query::Run("ls")));
+        assert!(!expr.evaluate(&Query::Run("curl")));
+        assert!(!expr.evaluate(&Query::Net));
+    }
+
+    #[test]
+    fn evaluate_all_leaves_unmentioned_categories_permissive() {
+        // `all(run("ls"), not(net))` says nothing about read/write/env -
+        // those should keep the crate's usual permissive default, not be
+        // denied just because every leaf in the `all()` is false for them.
+        let expr = parse(r#"all(run("ls"), not(net))"#).unwrap();
+        assert!(expr.evaluate(&Query::Read(Path::new("/etc/passwd"))));
+        assert!(expr.evaluate(&Query::Write(Path::new("/var/log/out.log"))));
+        assert!(expr.evaluate(&Query::Env("HOME")));
+    }
+
+    #[test]
+    fn evaluate_any_read_write() {
+        let expr = parse(r#"any(read("/tmp"), write("/var/log"))"#).unwrap();
+        assert!(expr.evaluate(&Query::Read(Path::new("/tmp/input.txt"))));
+        assert!(expr.evaluate(&Query::Write(Path::new("/var/log/out.log"))));
+        assert!(!expr.evaluate(&Query::Read(Path::new("/etc/passwd"))));
+        // `run` is a category this expression never mentions, so it
+        // defaults to permitted rather than being denied by proximity.
+        assert!(expr.evaluate(&Query::Run("ls")));
+    }
+
+    #[test]
+    fn policy_from_expr_routes_through_evaluate() {
+        let expr = parse(r#"all(run("ls"), not(net))"#).unwrap();
+        let policy = Policy::from_expr(expr);
+        assert!(policy.can_run("ls"));
+        assert!(!policy.can_run("curl"));
+        assert!(!policy.net_allowed());
+    }
+}
@@ -0,0 +1,255 @@
+//! Policy-Checked Filesystem Module
+//!
+//! Wraps `read_to_string`, `write`, `remove_file`, `open`/`create` and
+//! `OpenOptions` so they consult the active [`crate::policy`] before
+//! acting. The other `std::fs` free functions re-exported below (`read`,
+//! `copy`, `rename`, `remove_dir_all`, ...) and the types (`DirEntry`,
+//! `Metadata`, ...) are re-exported unchanged and are not policy-checked.
+
+use crate::policy;
+use crate::WormError;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// `File` is intentionally NOT re-exported here: `File::open`/`File::create`
+// are inherent associated functions, so re-exporting the type would bring
+// them along unchecked and let callers bypass `check_read`/`check_write`
+// entirely (the single most common way to open a file in Rust). Use the
+// policy-checked `open`/`create` functions or `OpenOptions` below instead;
+// `std::fs::File` itself is still a fine type to name for the value they
+// return.
+pub use std::fs::{
+    canonicalize, copy, create_dir, create_dir_all, metadata, read, read_dir, read_link,
+    remove_dir, remove_dir_all, rename, set_permissions, symlink_metadata, DirBuilder, DirEntry,
+    FileType, Metadata, Permissions, ReadDir,
+};
+
+
+
+/// Resolves `path` to an absolute, symlink-free form for policy checks,
+/// without requiring the path itself to exist (needed for writes/creates):
+/// falls back to canonicalizing the parent directory and rejoining the
+/// file name.
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    if let Ok(canon) = path.canonicalize() {
+        return Ok(canon);
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?;
+    let parent = match path.parent() {
+        Some(p) if !p.as_os_str().is_empty() => p,
+        _ => Path::new("."),
+    };
+    Ok(parent.canonicalize()?.join(file_name))
+}
+
+fn security_error(path: &Path, action: &str) -> io::Error {
+    let err = WormError::SecurityViolation(format!(
+        "{} denied by sandbox policy: '{}'",
+        action,
+        path.display()
+    ));
+    crate::audit::log_path_denied(action, path);
+    io::Error::new(io::ErrorKind::PermissionDenied, err)
+}
+
+fn check_read(path: &Path) -> io::Result<()> {
+    let resolved = resolve(path)?;
+    if !policy::current_policy().can_read(&resolved) {
+        return Err(security_error(&resolved, "read"));
+    }
+    Ok(())
+}
+
+fn check_write(path: &Path) -> io::Result<()> {
+    let resolved = resolve(path)?;
+    if !policy::current_policy().can_write(&resolved) {
+        return Err(security_error(&resolved, "write"));
+    }
+    Ok(())
+}
+
+/// Reads the entire contents of a file into a string.
+///
+/// Returns `io::ErrorKind::PermissionDenied` wrapping
+/// `WormError::SecurityViolation` if the path escapes the active
+/// policy's allowed read prefixes.
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    check_read(path.as_ref())?;
+    std::fs::read_to_string(path)
+}
+
+/// Writes a slice as the entire contents of a file, creating it if it
+/// doesn't exist and truncating it otherwise.
+///
+/// Returns `io::ErrorKind::PermissionDenied` wrapping
+/// `WormError::SecurityViolation` if the path escapes the active
+/// policy's allowed write prefixes.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    check_write(path.as_ref())?;
+    std::fs::write(path, contents)
+}
+
+/// Removes a file.
+///
+/// Returns `io::ErrorKind::PermissionDenied` wrapping
+/// `WormError::SecurityViolation` if the path escapes the active
+/// policy's allowed write prefixes.
+pub fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    check_write(path.as_ref())?;
+    std::fs::remove_file(path)
+}
+
+/// Opens a file in read-only mode, matching `std::fs::File::open`.
+///
+/// Returns `io::ErrorKind::PermissionDenied` wrapping
+/// `WormError::SecurityViolation` if the path escapes the active
+/// policy's allowed read prefixes.
+pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    OpenOptions::new().read(true).open(path)
+}
+
+/// Opens a file in write-only mode, creating it if needed and truncating
+/// it otherwise, matching `std::fs::File::create`.
+///
+/// Returns `io::ErrorKind::PermissionDenied` wrapping
+/// `WormError::SecurityViolation` if the path escapes the active
+/// policy's allowed write prefixes.
+pub fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+    OpenOptions::new().write(true).create(true).truncate(true).open(path)
+}
+
+/// A policy-checked version of `std::fs::OpenOptions`.
+///
+/// Mirrors the `std::fs::OpenOptions` builder; `open` consults the
+/// active policy for a read and/or write capability depending on which
+/// options were set before delegating to `std::fs::OpenOptions::open`.
+#[derive(Clone, Debug)]
+pub struct OpenOptions {
+    inner: std::fs::OpenOptions,
+    wants_read: bool,
+    wants_write: bool,
+}
+
+impl Default for OpenOptions {
+    fn default() -> Self {
+        OpenOptions::new()
+    }
+}
+
+impl OpenOptions {
+    /// Creates a blank set of options, matching `std::fs::OpenOptions::new`.
+    pub fn new() -> OpenOptions {
+        OpenOptions {
+            inner: std::fs::OpenOptions::new(),
+            wants_read: false,
+            wants_write: false,
+        }
+    }
+
+    pub fn read(&mut self, read: bool) -> &mut OpenOptions {
+        self.wants_read = read;
+        self.inner.read(read);
+        self
+    }
+
+    pub fn write(&mut self, write: bool) -> &mut OpenOptions {
+        self.wants_write = write;
+        self.inner.write(write);
+        self
+    }
+
+    pub fn append(&mut self, append: bool) -> &mut OpenOptions {
+        self.wants_write = self.wants_write || append;
+        self.inner.append(append);
+        self
+    }
+
+    pub fn truncate(&mut self, truncate: bool) -> &mut OpenOptions {
+        self.wants_write = self.wants_write || truncate;
+        self.inner.truncate(truncate);
+        self
+    }
+
+    pub fn create(&mut self, create: bool) -> &mut OpenOptions {
+        self.wants_write = self.wants_write || create;
+        self.inner.create(create);
+        self
+    }
+
+    pub fn create_new(&mut self, create_new: bool) -> &mut OpenOptions {
+        self.wants_write = self.wants_write || create_new;
+        self.inner.create_new(create_new);
+        self
+    }
+
+    /// Opens the file at `path` with these options, after checking the
+    /// active policy for whichever of read/write capability was
+    /// requested.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        if self.wants_read {
+            check_read(path.as_ref())?;
+        }
+        if self.wants_write {
+            check_write(path.as_ref())?;
+        }
+        self.inner.open(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::policy::{set_policy, Policy, POLICY_TEST_LOCK};
+
+    #[test]
+    fn write_denied_outside_allowed_prefix() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join("worm_std_fs_test_denied");
+        let _ = std::fs::create_dir_all(&dir);
+        set_policy(Policy::builder().allow_write(["/nonexistent-allowed-prefix"]).build());
+
+        let result = write(dir.join("out.txt"), "hello");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+        set_policy(Policy::allow_all());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn write_allowed_under_allowed_prefix() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let dir = std::env::temp_dir().join("worm_std_fs_test_allowed");
+        let _ = std::fs::create_dir_all(&dir);
+        let canon_dir = dir.canonicalize().unwrap();
+        set_policy(Policy::builder().allow_write([canon_dir.clone()]).build());
+
+        let result = write(dir.join("out.txt"), "hello");
+        assert!(result.is_ok());
+
+        set_policy(Policy::allow_all());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn open_and_create_are_policy_checked() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        set_policy(Policy::builder().allow_read(["/nonexistent-allowed-prefix"]).build());
+
+        let result = open("/etc/hostname");
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+        set_policy(Policy::builder().allow_write(["/nonexistent-allowed-prefix"]).build());
+
+        let result = create(std::env::temp_dir().join("worm_std_fs_test_create.txt"));
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+        set_policy(Policy::allow_all());
+    }
+}
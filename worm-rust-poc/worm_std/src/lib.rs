@@ -60,7 +60,6 @@ pub use std::{
     f64,
     ffi,
     fmt,
-    fs,
     future,
     hash,
     hint,
@@ -122,6 +121,44 @@ pub use std::{
 
 pub mod process;
 
+// ============================================================================
+// AUDIT LOG
+// ============================================================================
+
+/// Structured, newline-delimited JSON log of security decisions
+pub mod audit;
+
+// ============================================================================
+// POLICY-CHECKED FILESYSTEM MODULE
+// ============================================================================
+
+/// Policy-checked filesystem operations
+pub mod fs;
+
+// ============================================================================
+// SANDBOX POLICY
+// ============================================================================
+
+/// Configurable allow/deny capability policy
+pub mod policy;
+
+/// `cfg(...)`-style policy expression files
+pub mod config;
+
+// ============================================================================
+// URL/HOST DETECTION
+// ============================================================================
+
+/// URL and network-authority parsing for argument/environment scanning
+pub mod url_detect;
+
+// ============================================================================
+// SANDBOX
+// ============================================================================
+
+/// Kernel-enforced seccomp-BPF syscall filtering
+pub mod sandbox;
+
 // ============================================================================
 // WORM-SPECIFIC MODULES
 // ============================================================================
@@ -0,0 +1,424 @@
+//! Sandbox Policy
+//!
+//! The command and filesystem checks in [`crate::process`] and
+//! [`crate::fs`] used to consult a single hardcoded blocklist. [`Policy`]
+//! replaces that with a configurable allow/deny model (in the spirit of
+//! Deno's permission flags): each program declares the capabilities it
+//! needs - which commands it may run, which paths it may read or write,
+//! which environment variables it may see - instead of getting whatever
+//! the crate author hardcoded.
+//!
+//! The default policy denies network access (the one thing this crate
+//! cannot safely allow) and otherwise allows everything, matching the
+//! crate's previous behavior.
+//!
+//! A [`Policy`] can also come from a `worm.policy` expression file via
+//! [`crate::config::load_policy_file`] instead of [`Policy::builder`] -
+//! see [`crate::config`] for the grammar.
+
+use crate::config::{CfgExpr, Query};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{OnceLock, RwLock};
+
+/// A sandbox capability policy.
+///
+/// Build one with [`Policy::builder`] and install it process-wide with
+/// [`set_policy`]. `process::Command` and `fs` consult the active policy
+/// on every call instead of a fixed blocklist.
+#[derive(Debug, Clone)]
+pub struct Policy {
+    allow_run: Option<HashSet<String>>,
+    deny_run: HashSet<String>,
+    allow_read: Option<Vec<PathBuf>>,
+    allow_write: Option<Vec<PathBuf>>,
+    allow_env: Option<HashSet<String>>,
+    allow_net: bool,
+    allow_host: Option<HashSet<String>>,
+    deny_host: HashSet<String>,
+    block_url_patterns: bool,
+    /// When set (loaded from a `worm.policy` file), this expression is
+    /// the source of truth for `can_run`/`can_read`/`can_write`/
+    /// `can_see_env`/`net_allowed` instead of the fields above.
+    expr: Option<CfgExpr>,
+}
+
+impl Policy {
+    /// Starts building a policy. Defaults to deny-network,
+    /// allow-everything-else, matching the crate's previous behavior.
+    pub fn builder() -> PolicyBuilder {
+        PolicyBuilder::default()
+    }
+
+    /// The permissive default: no command, path, or env restrictions,
+    /// network access denied.
+    pub fn allow_all() -> Policy {
+        Policy::builder().build()
+    }
+
+    /// Builds a policy whose capability checks are entirely driven by a
+    /// parsed `worm.policy` expression. Prefer
+    /// [`crate::config::load_policy_file`] to load one from disk.
+    pub fn from_expr(expr: CfgExpr) -> Policy {
+        Policy {
+            expr: Some(expr),
+            ..Policy::allow_all()
+        }
+    }
+
+    pub(crate) fn can_run(&self, program: &str) -> bool {
+        if let Some(expr) = &self.expr {
+            return expr.evaluate(&Query::Run(program));
+        }
+
+        let base = base_name(program);
+        if self.deny_run.contains(base) || self.deny_run.contains(program) {
+            return false;
+        }
+        match &self.allow_run {
+            Some(allowed) => allowed.contains(base) || allowed.contains(program),
+            None => true,
+        }
+    }
+
+    pub(crate) fn can_read(&self, path: &Path) -> bool {
+        if let Some(expr) = &self.expr {
+            return expr.evaluate(&Query::Read(path));
+        }
+        Self::path_allowed(&self.allow_read, path)
+    }
+
+    pub(crate) fn can_write(&self, path: &Path) -> bool {
+        if let Some(expr) = &self.expr {
+            return expr.evaluate(&Query::Write(path));
+        }
+        Self::path_allowed(&self.allow_write, path)
+    }
+
+    pub(crate) fn can_see_env(&self, var: &str) -> bool {
+        if let Some(expr) = &self.expr {
+            return expr.evaluate(&Query::Env(var));
+        }
+        match &self.allow_env {
+            Some(allowed) => allowed.contains(var),
+            None => true,
+        }
+    }
+
+    pub(crate) fn net_allowed(&self) -> bool {
+        if let Some(expr) = &self.expr {
+            return expr.evaluate(&Query::Net);
+        }
+        self.allow_net
+    }
+
+    /// Whether `host` (as extracted from a detected URL/authority) is
+    /// permitted under warn-mode detection. Checked against `deny_host`
+    /// first, then `allow_host` if set; like the rest of `Policy`'s
+    /// fields, an unset `allow_host` is permissive.
+    pub(crate) fn can_access_host(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        if self.deny_host.contains(&host) {
+            return false;
+        }
+        match &self.allow_host {
+            Some(allowed) => allowed.contains(&host),
+            None => true,
+        }
+    }
+
+    /// Whether `host` is permitted under hard-block detection
+    /// (`block_url_patterns`). Unlike `can_access_host`, an unset
+    /// `allow_host` denies rather than permits - hard-blocking is an
+    /// explicit-allowlist mode, matching the crate's deny-by-default
+    /// stance on network access.
+    pub(crate) fn can_access_host_strict(&self, host: &str) -> bool {
+        let host = host.to_lowercase();
+        if self.deny_host.contains(&host) {
+            return false;
+        }
+        match &self.allow_host {
+            Some(allowed) => allowed.contains(&host),
+            None => false,
+        }
+    }
+
+    /// Whether a detected URL/authority should be a hard block
+    /// (`WormError::UrlPatternDetected`) rather than an audit-logged
+    /// warning.
+    pub(crate) fn url_detection_is_hard_block(&self) -> bool {
+        self.block_url_patterns
+    }
+
+    fn path_allowed(allowed: &Option<Vec<PathBuf>>, path: &Path) -> bool {
+        match allowed {
+            None => true,
+            Some(prefixes) => prefixes.iter().any(|prefix| path.starts_with(prefix)),
+        }
+    }
+}
+
+fn base_name(program: &str) -> &str {
+    Path::new(program)
+        .file_name()
+        .and_then(|s| s.to_str())
+        .unwrap_or(program)
+}
+
+/// Builds a [`Policy`].
+#[derive(Debug, Default)]
+pub struct PolicyBuilder {
+    allow_run: Option<HashSet<String>>,
+    deny_run: HashSet<String>,
+    allow_read: Option<Vec<PathBuf>>,
+    allow_write: Option<Vec<PathBuf>>,
+    allow_env: Option<HashSet<String>>,
+    allow_net: bool,
+    allow_host: Option<HashSet<String>>,
+    deny_host: HashSet<String>,
+    block_url_patterns: bool,
+}
+
+impl PolicyBuilder {
+    /// Restricts `process::Command::new` to this set of program names or
+    /// absolute paths. Without this call, any program not in
+    /// [`deny_run`](Self::deny_run) is allowed.
+    pub fn allow_run<I, S>(mut self, programs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_run
+            .get_or_insert_with(HashSet::new)
+            .extend(programs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Blocks these program names or absolute paths even if they would
+    /// otherwise be allowed. Checked before `allow_run`.
+    pub fn deny_run<I, S>(mut self, programs: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.deny_run.extend(programs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Restricts `fs` reads to paths under one of these prefixes. Without
+    /// this call, any path is readable.
+    pub fn allow_read<I, P>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.allow_read
+            .get_or_insert_with(Vec::new)
+            .extend(prefixes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Restricts `fs` writes to paths under one of these prefixes. Without
+    /// this call, any path is writable.
+    pub fn allow_write<I, P>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        self.allow_write
+            .get_or_insert_with(Vec::new)
+            .extend(prefixes.into_iter().map(Into::into));
+        self
+    }
+
+    /// Restricts which environment variables `process::Command::env` may
+    /// set/forward. Without this call, any variable is allowed.
+    pub fn allow_env<I, S>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_env
+            .get_or_insert_with(HashSet::new)
+            .extend(vars.into_iter().map(Into::into));
+        self
+    }
+
+    /// Allows network access. Off by default - this is the one
+    /// capability the crate denies unless explicitly opted into.
+    pub fn allow_net(mut self, allow: bool) -> Self {
+        self.allow_net = allow;
+        self
+    }
+
+    /// Restricts hosts detected in command arguments/environment to this
+    /// set. Without this call, any host is permitted in the default warn
+    /// mode (the URL/host scan still runs and is audit-logged, it just
+    /// doesn't block), while with [`block_url_patterns`](Self::block_url_patterns)
+    /// every detected host is blocked. Matched
+    /// against the normalized host extracted by `url_detect`, not raw
+    /// text.
+    pub fn allow_host<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allow_host
+            .get_or_insert_with(HashSet::new)
+            .extend(hosts.into_iter().map(|h| h.into().to_lowercase()));
+        self
+    }
+
+    /// Blocks these hosts even if they would otherwise be allowed.
+    /// Checked before `allow_host`.
+    pub fn deny_host<I, S>(mut self, hosts: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.deny_host.extend(hosts.into_iter().map(|h| h.into().to_lowercase()));
+        self
+    }
+
+    /// Makes detecting a URL/network authority in command
+    /// arguments/environment a hard block (`WormError::UrlPatternDetected`)
+    /// instead of an audit-logged warning. Off by default, matching the
+    /// crate's previous warn-only behavior.
+    pub fn block_url_patterns(mut self, block: bool) -> Self {
+        self.block_url_patterns = block;
+        self
+    }
+
+    /// Finishes building the policy.
+    pub fn build(self) -> Policy {
+        Policy {
+            allow_run: self.allow_run,
+            deny_run: self.deny_run,
+            allow_read: self.allow_read,
+            allow_write: self.allow_write,
+            allow_env: self.allow_env,
+            allow_net: self.allow_net,
+            allow_host: self.allow_host,
+            deny_host: self.deny_host,
+            block_url_patterns: self.block_url_patterns,
+            expr: None,
+        }
+    }
+}
+
+static ACTIVE_POLICY: OnceLock<RwLock<Policy>> = OnceLock::new();
+
+/// Installs the process-wide policy that `process::Command` and `fs`
+/// consult. Intended to be called once at startup; a later call replaces
+/// the previously active policy.
+pub fn set_policy(policy: Policy) {
+    match ACTIVE_POLICY.get() {
+        Some(lock) => {
+            *lock.write().unwrap() = policy;
+        }
+        None => {
+            let _ = ACTIVE_POLICY.set(RwLock::new(policy));
+        }
+    }
+}
+
+/// Returns a clone of the currently active policy, or the permissive
+/// default if none has been installed.
+pub fn current_policy() -> Policy {
+    ACTIVE_POLICY
+        .get_or_init(|| RwLock::new(Policy::allow_all()))
+        .read()
+        .unwrap()
+        .clone()
+}
+
+/// Serializes tests (here and in `fs`/`process`) that call `set_policy`:
+/// `ACTIVE_POLICY` is global process state, and `cargo test` runs tests in
+/// parallel threads by default, so without this lock one test's policy
+/// change leaks into another test running at the same time. Acquire with
+/// `.lock().unwrap_or_else(|e| e.into_inner())` so a prior panic while
+/// holding the lock doesn't poison every later test.
+#[cfg(test)]
+pub(crate) static POLICY_TEST_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_policy_allows_everything_but_network() {
+        let policy = Policy::allow_all();
+        assert!(policy.can_run("curl"));
+        assert!(policy.can_read(Path::new("/etc/passwd")));
+        assert!(policy.can_write(Path::new("/tmp/out")));
+        assert!(policy.can_see_env("HOME"));
+        assert!(!policy.net_allowed());
+    }
+
+    #[test]
+    fn allow_run_restricts_to_allowlist() {
+        let policy = Policy::builder().allow_run(["ls", "cat"]).build();
+        assert!(policy.can_run("ls"));
+        assert!(policy.can_run("/usr/bin/cat"));
+        assert!(!policy.can_run("rm"));
+    }
+
+    #[test]
+    fn deny_run_overrides_allowlist() {
+        let policy = Policy::builder().allow_run(["curl"]).deny_run(["curl"]).build();
+        assert!(!policy.can_run("curl"));
+    }
+
+    #[test]
+    fn allow_read_and_write_restrict_to_prefixes() {
+        let policy = Policy::builder()
+            .allow_read(["/tmp"])
+            .allow_write(["/tmp/out"])
+            .build();
+        assert!(policy.can_read(Path::new("/tmp/input.txt")));
+        assert!(!policy.can_read(Path::new("/etc/passwd")));
+        assert!(policy.can_write(Path::new("/tmp/out/result.txt")));
+        assert!(!policy.can_write(Path::new("/tmp/other.txt")));
+    }
+
+    #[test]
+    fn allow_env_restricts_to_allowlist() {
+        let policy = Policy::builder().allow_env(["PATH"]).build();
+        assert!(policy.can_see_env("PATH"));
+        assert!(!policy.can_see_env("SECRET_TOKEN"));
+    }
+
+    #[test]
+    fn allow_host_restricts_to_allowlist() {
+        let policy = Policy::builder().allow_host(["internal.example.com"]).build();
+        assert!(policy.can_access_host("internal.example.com"));
+        assert!(policy.can_access_host("Internal.Example.Com"));
+        assert!(!policy.can_access_host("evil.com"));
+    }
+
+    #[test]
+    fn deny_host_overrides_allowlist() {
+        let policy = Policy::builder()
+            .allow_host(["evil.com"])
+            .deny_host(["evil.com"])
+            .build();
+        assert!(!policy.can_access_host("evil.com"));
+    }
+
+    #[test]
+    fn strict_host_check_denies_by_default() {
+        let policy = Policy::allow_all();
+        assert!(!policy.can_access_host_strict("anything.example.com"));
+        let policy = Policy::builder().allow_host(["ok.example.com"]).build();
+        assert!(policy.can_access_host_strict("ok.example.com"));
+        assert!(!policy.can_access_host_strict("other.example.com"));
+    }
+
+    #[test]
+    fn url_detection_defaults_to_warn_not_block() {
+        let policy = Policy::allow_all();
+        assert!(!policy.url_detection_is_hard_block());
+        let policy = Policy::builder().block_url_patterns(true).build();
+        assert!(policy.url_detection_is_hard_block());
+    }
+}
@@ -3,6 +3,8 @@
 //! This module provides a filtered version of std::process that blocks
 //! network-related commands while allowing safe local operations.
 
+use crate::policy;
+use crate::url_detect;
 use crate::WormError;
 use std::ffi::OsStr;
 use std::io;
@@ -10,8 +12,50 @@ use std::io;
 // Re-export safe types from std::process
 pub use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, ExitStatus, Output, Stdio};
 
-/// List of blocked commands that could be used for network access
-const BLOCKED_COMMANDS: &[&str] = &[
+/// Network-namespace isolation for spawned children.
+///
+/// Command-name filtering is trivially defeated by renaming a binary or
+/// using a static one, so this provides a stronger, Linux-only
+/// guarantee: the child runs in a fresh network namespace with no
+/// routes and no interfaces besides (at most) loopback, so no socket it
+/// opens can reach anywhere. It composes with [`crate::sandbox`]'s
+/// seccomp filter as layered defense - the namespace means there's
+/// nothing to connect to even if a syscall the filter would otherwise
+/// block somehow ran.
+#[cfg(target_os = "linux")]
+mod netns {
+    use std::io;
+    use std::os::raw::c_int;
+
+    const CLONE_NEWNET: c_int = 0x4000_0000;
+
+    extern "C" {
+        fn unshare(flags: c_int) -> c_int;
+    }
+
+    /// Runs in the child, between `fork` and `exec` (see
+    /// `CommandExt::pre_exec`), so it must be async-signal-safe: only the
+    /// `unshare` syscall itself, no allocation. `io::Error::last_os_error`
+    /// just wraps the raw errno (no formatting, no allocation), so on
+    /// failure this returns that unchanged rather than building a
+    /// descriptive message here - `Command::spawn`/`output`/`status`
+    /// attach the EPERM explanation once control is back in the parent,
+    /// where allocating is safe.
+    pub fn unshare_network() -> io::Result<()> {
+        if unsafe { unshare(CLONE_NEWNET) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+/// Commands that are blocked whenever the active policy denies network
+/// access (`Policy::allow_net` left at its default of `false`), since
+/// they can reach the network. A policy can still widen `deny_run`
+/// further, but `allow_run` cannot unblock these - only
+/// `Policy::builder().allow_net(true)` does.
+const NETWORK_COMMANDS: &[&str] = &[
     // Network clients
     "curl",
     "wget",
@@ -43,33 +87,35 @@ const BLOCKED_COMMANDS: &[&str] = &[
     "rsync", // Can use network
 ];
 
-/// URL patterns that might indicate network access
-const URL_PATTERNS: &[&str] = &["http://", "https://", "ftp://", "ssh://", "tcp://", "udp://"];
-
-/// Check if a command is blocked
-fn is_blocked_command(program: &str) -> bool {
+/// Check if a command is blocked: either it's a known network command
+/// (and the active policy hasn't opted into `allow_net`), or the active
+/// policy's `allow_run`/`deny_run` rules reject it.
+fn is_blocked_command(program: &str, policy: &policy::Policy) -> bool {
     let program_lower = program.to_lowercase();
     let base_cmd = std::path::Path::new(&program_lower)
         .file_name()
         .and_then(|s| s.to_str())
         .unwrap_or(&program_lower);
 
-    BLOCKED_COMMANDS.contains(&base_cmd)
-}
+    if !policy.net_allowed() && NETWORK_COMMANDS.contains(&base_cmd) {
+        return true;
+    }
 
-/// Check if a string contains URL patterns
-fn contains_url_pattern(s: &str) -> bool {
-    URL_PATTERNS.iter().any(|pattern| s.contains(pattern))
+    !policy.can_run(program)
 }
 
 /// A restricted version of std::process::Command
 ///
 /// This command builder blocks execution of network-related commands
-/// and warns about URL patterns in arguments.
+/// and scans arguments/environment for URL patterns, warning (or, with
+/// `Policy::block_url_patterns`, hard-blocking) on any host not covered
+/// by the active policy's host allowlist.
 pub struct Command {
     inner: std::process::Command,
     program: String,
     has_url_warning: bool,
+    policy_violation: Option<WormError>,
+    network_isolation_requested: bool,
 }
 
 impl Command {
@@ -94,8 +140,9 @@ impl Command {
     pub fn new<S: AsRef<OsStr>>(program: S) -> Result<Command, WormError> {
         let program_str = program.as_ref().to_string_lossy().to_string();
 
-        // Security check: block network commands
-        if is_blocked_command(&program_str) {
+        // Security check: consult the active policy
+        if is_blocked_command(&program_str, &policy::current_policy()) {
+            crate::audit::log_blocked_command(&program_str, "command blocked by sandbox policy");
             return Err(WormError::NetworkCommandBlocked(program_str));
         }
 
@@ -103,27 +150,22 @@ impl Command {
             inner: std::process::Command::new(program),
             program: program_str,
             has_url_warning: false,
+            policy_violation: None,
+            network_isolation_requested: false,
         })
     }
 
     /// Adds an argument to the command
     ///
-    /// Warns if the argument contains URL patterns
+    /// Scans the argument for URLs/network authorities (see
+    /// `url_detect`) and checks any host found against the active
+    /// policy's host allowlist/denylist. Depending on
+    /// `Policy::block_url_patterns`, a disallowed host either warns (the
+    /// default) or is recorded as a policy violation surfaced when the
+    /// command is spawned.
     pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
-        let arg_str = arg.as_ref().to_string_lossy();
-
-        // Warning: check for URLs in arguments
-        if contains_url_pattern(&arg_str) {
-            if !self.has_url_warning {
-                eprintln!(
-                    "⚠️  WORM WARNING: URL pattern detected in command '{}': {}",
-                    self.program, arg_str
-                );
-                eprintln!("   This may be attempting network access");
-                self.has_url_warning = true;
-            }
-        }
-
+        let arg_str = arg.as_ref().to_string_lossy().into_owned();
+        self.check_url_patterns(&arg_str);
         self.inner.arg(arg);
         self
     }
@@ -141,17 +183,43 @@ impl Command {
     }
 
     /// Sets the working directory
+    ///
+    /// Recorded as a policy violation (surfaced when the command is
+    /// spawned) if `dir` falls outside the active policy's allowed read
+    /// prefixes.
     pub fn current_dir<P: AsRef<std::path::Path>>(&mut self, dir: P) -> &mut Command {
+        let dir = dir.as_ref();
+        if self.policy_violation.is_none() && !policy::current_policy().can_read(dir) {
+            crate::audit::log_path_denied("current_dir", dir);
+            self.policy_violation = Some(WormError::SecurityViolation(format!(
+                "working directory denied by sandbox policy: '{}'",
+                dir.display()
+            )));
+        }
         self.inner.current_dir(dir);
         self
     }
 
     /// Sets an environment variable
+    ///
+    /// Recorded as a policy violation (surfaced when the command is
+    /// spawned) if `key` isn't in the active policy's allowed env vars.
+    /// The value is also scanned for URLs/network authorities, since a
+    /// piped-in env var is as good an exfiltration vector as an argument.
     pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Command
     where
         K: AsRef<OsStr>,
         V: AsRef<OsStr>,
     {
+        let key_str = key.as_ref().to_string_lossy().into_owned();
+        let val_str = val.as_ref().to_string_lossy().into_owned();
+        if self.policy_violation.is_none() && !policy::current_policy().can_see_env(&key_str) {
+            self.policy_violation = Some(WormError::SecurityViolation(format!(
+                "environment variable denied by sandbox policy: '{}'",
+                key_str
+            )));
+        }
+        self.check_url_patterns(&val_str);
         self.inner.env(key, val);
         self
     }
@@ -186,28 +254,124 @@ impl Command {
         self
     }
 
+    /// Runs the child in a fresh network namespace (Linux only), so it
+    /// gets no routes and no interfaces besides loopback - no socket it
+    /// opens can reach anywhere, regardless of command-name filtering.
+    ///
+    /// Combine with command filtering and [`crate::sandbox`]'s seccomp
+    /// filter for layered defense: renaming a blocked binary no longer
+    /// helps once its syscalls have nothing reachable to connect to.
+    ///
+    /// If the kernel refuses the namespace (`EPERM` - the process lacks
+    /// `CAP_SYS_ADMIN`; no user namespace is created to obtain it), the
+    /// failure surfaces from
+    /// `spawn`/`output`/`status` as `WormError::SecurityViolation`,
+    /// wrapped in an `io::Error` with `ErrorKind::PermissionDenied`.
+    ///
+    /// On non-Linux targets this is a no-op: there is no namespace to
+    /// isolate into.
+    pub fn isolate_network(&mut self, enable: bool) -> &mut Command {
+        #[cfg(target_os = "linux")]
+        if enable {
+            self.network_isolation_requested = true;
+            // SAFETY: `unshare_network` only calls the `unshare` syscall
+            // and allocates nothing - safe to run between `fork` and `exec`.
+            unsafe {
+                use std::os::unix::process::CommandExt;
+                self.inner.pre_exec(netns::unshare_network);
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        let _ = enable;
+
+        self
+    }
+
+    /// Attaches a descriptive `WormError::SecurityViolation` to a
+    /// `PermissionDenied` error from a spawn we asked to network-isolate,
+    /// since `unshare_network` can't safely build that message itself
+    /// (see its doc comment). Runs in the parent, after `fork` has
+    /// returned, where allocating is fine.
+    fn contextualize_netns_error(&self, err: io::Error) -> io::Error {
+        if self.network_isolation_requested && err.kind() == io::ErrorKind::PermissionDenied {
+            io::Error::new(
+                io::ErrorKind::PermissionDenied,
+                WormError::SecurityViolation(format!(
+                    "network namespace isolation requires CAP_SYS_ADMIN \
+                     (unshare(CLONE_NEWNET) failed: {})",
+                    err
+                )),
+            )
+        } else {
+            err
+        }
+    }
+
+    /// Scans `text` for URL/network-authority candidates and checks each
+    /// extracted host against the active policy.
+    fn check_url_patterns(&mut self, text: &str) {
+        let policy = policy::current_policy();
+        let hard_block = policy.url_detection_is_hard_block();
+
+        for url in url_detect::scan(text) {
+            let host = url.normalized_host().to_string();
+            let permitted = if hard_block {
+                policy.can_access_host_strict(&host)
+            } else {
+                policy.can_access_host(&host)
+            };
+            if permitted {
+                crate::audit::log_url_pattern(&self.program, &host, crate::audit::Action::Allowed);
+                continue;
+            }
+
+            if hard_block {
+                crate::audit::log_url_pattern(&self.program, &host, crate::audit::Action::Blocked);
+                if self.policy_violation.is_none() {
+                    self.policy_violation = Some(WormError::UrlPatternDetected(host));
+                }
+            } else if !self.has_url_warning {
+                crate::audit::log_url_pattern(&self.program, &host, crate::audit::Action::Warned);
+                self.has_url_warning = true;
+            }
+        }
+    }
+
+    /// Returns the recorded policy violation, if any, as an `io::Error`.
+    fn check_policy(&self) -> io::Result<()> {
+        match &self.policy_violation {
+            Some(err) => Err(io::Error::new(io::ErrorKind::PermissionDenied, err.clone())),
+            None => Ok(()),
+        }
+    }
+
     /// Spawns the command
     pub fn spawn(&mut self) -> io::Result<Child> {
-        self.inner.spawn()
+        self.check_policy()?;
+        self.inner.spawn().map_err(|e| self.contextualize_netns_error(e))
     }
 
     /// Executes the command and waits for completion
     pub fn output(&mut self) -> io::Result<Output> {
-        self.inner.output()
+        self.check_policy()?;
+        self.inner.output().map_err(|e| self.contextualize_netns_error(e))
     }
 
     /// Executes the command and waits for it to finish
     pub fn status(&mut self) -> io::Result<ExitStatus> {
-        self.inner.status()
+        self.check_policy()?;
+        self.inner.status().map_err(|e| self.contextualize_netns_error(e))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::policy::POLICY_TEST_LOCK;
 
     #[test]
     fn test_allowed_commands() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         // These should all succeed
         assert!(Command::new("ls").is_ok());
         assert!(Command::new("cat").is_ok());
@@ -218,6 +382,7 @@ mod tests {
 
     #[test]
     fn test_blocked_commands() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
         // These should all fail
         assert!(Command::new("curl").is_err());
         assert!(Command::new("wget").is_err());
@@ -227,10 +392,57 @@ mod tests {
     }
 
     #[test]
-    fn test_url_detection() {
-        assert!(contains_url_pattern("http://example.com"));
-        assert!(contains_url_pattern("https://evil.com"));
-        assert!(contains_url_pattern("ftp://server.com"));
-        assert!(!contains_url_pattern("normal string"));
+    fn test_url_pattern_hard_block() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        // Hard-block mode is an explicit-allowlist mode: any host not in
+        // `allow_host` is denied, so `block_url_patterns` alone is enough.
+        policy::set_policy(policy::Policy::builder().block_url_patterns(true).build());
+
+        let mut cmd = Command::new("echo").unwrap();
+        cmd.arg("http://evil.com/exfiltrate");
+        let result = cmd.output();
+        assert!(result.is_err());
+        assert_eq!(result.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+
+        policy::set_policy(policy::Policy::allow_all());
+    }
+
+    #[test]
+    fn test_url_pattern_warn_only_by_default() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        let mut cmd = Command::new("echo").unwrap();
+        cmd.arg("http://evil.com/exfiltrate");
+        assert!(cmd.output().is_ok());
+    }
+
+    #[test]
+    fn test_isolate_network_either_succeeds_or_reports_permission_denied() {
+        // Requires CAP_SYS_ADMIN, which
+        // isn't guaranteed in every CI/sandbox environment, so accept
+        // either outcome - what matters is that a refusal surfaces as
+        // our own permission-denied error rather than a panic or hang.
+        let mut cmd = Command::new("true").unwrap();
+        cmd.isolate_network(true);
+        match cmd.status() {
+            Ok(status) => assert!(status.success()),
+            Err(e) => assert_eq!(e.kind(), io::ErrorKind::PermissionDenied),
+        }
+    }
+
+    #[test]
+    fn test_allowed_host_is_not_blocked() {
+        let _guard = POLICY_TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+        policy::set_policy(
+            policy::Policy::builder()
+                .block_url_patterns(true)
+                .allow_host(["internal.example.com"])
+                .build(),
+        );
+
+        let mut cmd = Command::new("echo").unwrap();
+        cmd.arg("http://internal.example.com/status");
+        assert!(cmd.output().is_ok());
+
+        policy::set_policy(policy::Policy::allow_all());
     }
 }
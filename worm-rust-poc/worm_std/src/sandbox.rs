@@ -0,0 +1,266 @@
+//! Process Sandbox
+//!
+//! Installs a kernel-enforced seccomp-BPF filter that blocks the syscalls
+//! used to open sockets, backing up the command/URL filtering in
+//! [`crate::process`] with a guarantee that holds even against `unsafe`
+//! FFI or a renamed network binary.
+//!
+//! Unlike the userspace checks in `process::Command`, this filter is
+//! enforced by the kernel on the thread that installs it and on every
+//! thread or process that thread creates afterwards (threads already
+//! running are not covered), so it cannot be bypassed by calling into libc
+//! directly or by invoking a binary under a different name.
+
+use crate::WormError;
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use super::WormError;
+    use std::io;
+    use std::os::raw::{c_int, c_ulong};
+
+    // include/uapi/linux/prctl.h
+    const PR_SET_NO_NEW_PRIVS: c_int = 38;
+    const PR_SET_SECCOMP: c_int = 22;
+
+    // include/uapi/linux/seccomp.h
+    const SECCOMP_MODE_FILTER: c_ulong = 2;
+
+    // include/uapi/linux/filter.h
+    const BPF_LD: u16 = 0x00;
+    const BPF_W: u16 = 0x00;
+    const BPF_ABS: u16 = 0x20;
+    const BPF_JMP: u16 = 0x05;
+    const BPF_JEQ: u16 = 0x10;
+    const BPF_K: u16 = 0x00;
+    const BPF_RET: u16 = 0x06;
+
+    // include/uapi/linux/seccomp.h - actions, high 16 bits select the
+    // action, low 16 bits are the errno for SECCOMP_RET_ERRNO.
+    const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+    const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+    const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+
+    const EPERM: u32 = 1;
+
+    // offsets into struct seccomp_data, see <linux/seccomp.h>
+    const SECCOMP_DATA_NR_OFFSET: u32 = 0;
+    const SECCOMP_DATA_ARCH_OFFSET: u32 = 4;
+
+    /// `AUDIT_ARCH_*` constants from `<linux/audit.h>`, used to pin the
+    /// filter to the architecture it was compiled for so a 32/64-bit
+    /// syscall-number collision can't smuggle a blocked syscall through.
+    #[cfg(target_arch = "x86_64")]
+    const AUDIT_ARCH_CURRENT: u32 = 0xc000_003e;
+    #[cfg(target_arch = "aarch64")]
+    const AUDIT_ARCH_CURRENT: u32 = 0xc000_00b7;
+
+    #[cfg(target_arch = "x86_64")]
+    mod nr {
+        pub const SOCKET: u32 = 41;
+        pub const CONNECT: u32 = 42;
+        pub const ACCEPT: u32 = 43;
+        pub const SENDTO: u32 = 44;
+        pub const RECVFROM: u32 = 45;
+        pub const SENDMSG: u32 = 46;
+        pub const RECVMSG: u32 = 47;
+        pub const BIND: u32 = 49;
+        pub const LISTEN: u32 = 50;
+        pub const SOCKETPAIR: u32 = 53;
+        pub const ACCEPT4: u32 = 288;
+    }
+
+    #[cfg(target_arch = "aarch64")]
+    mod nr {
+        pub const SOCKET: u32 = 198;
+        pub const SOCKETPAIR: u32 = 199;
+        pub const BIND: u32 = 200;
+        pub const CONNECT: u32 = 203;
+        pub const LISTEN: u32 = 201;
+        pub const ACCEPT: u32 = 202;
+        pub const RECVFROM: u32 = 207;
+        pub const SENDTO: u32 = 206;
+        pub const RECVMSG: u32 = 212;
+        pub const SENDMSG: u32 = 211;
+        pub const ACCEPT4: u32 = 242;
+    }
+
+    /// The syscalls a socket-opening exfiltration path can use.
+    const BLOCKED_SYSCALLS: &[u32] = &[
+        nr::SOCKET,
+        nr::SOCKETPAIR,
+        nr::CONNECT,
+        nr::BIND,
+        nr::LISTEN,
+        nr::ACCEPT,
+        nr::ACCEPT4,
+        nr::SENDTO,
+        nr::RECVFROM,
+        nr::SENDMSG,
+        nr::RECVMSG,
+    ];
+
+    extern "C" {
+        fn prctl(option: c_int, arg2: c_ulong, arg3: c_ulong, arg4: c_ulong, arg5: c_ulong) -> c_int;
+    }
+
+    /// A single BPF instruction, `struct sock_filter` from `<linux/filter.h>`.
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct SockFilter {
+        code: u16,
+        jt: u8,
+        jf: u8,
+        k: u32,
+    }
+
+    impl SockFilter {
+        const fn stmt(code: u16, k: u32) -> Self {
+            SockFilter { code, jt: 0, jf: 0, k }
+        }
+
+        const fn jump(code: u16, k: u32, jt: u8, jf: u8) -> Self {
+            SockFilter { code, jt, jf, k }
+        }
+    }
+
+    /// `struct sock_fprog` from `<linux/filter.h>`.
+    #[repr(C)]
+    struct SockFprog {
+        len: u16,
+        filter: *const SockFilter,
+    }
+
+    fn build_filter() -> Vec<SockFilter> {
+        let mut prog = vec![
+            // Load seccomp_data.arch and kill the process on any mismatch
+            // with the architecture this binary was compiled for, so a
+            // syscall-number collision between architectures can't be
+            // used to smuggle a blocked syscall past this filter.
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH_OFFSET),
+            SockFilter::jump(BPF_JMP | BPF_JEQ | BPF_K, AUDIT_ARCH_CURRENT, 1, 0),
+            SockFilter::stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+            // Load seccomp_data.nr for the syscall-number comparisons below.
+            SockFilter::stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR_OFFSET),
+        ];
+
+        // One jump-if-equal per blocked syscall. Each check is exactly two
+        // instructions (the jump itself and the `ret_errno` it guards), so
+        // a miss only ever needs to skip that one trailing instruction to
+        // fall through to the next check's jump - `jf` is always 1,
+        // regardless of how many checks remain. Falling through all of
+        // them reaches the final SECCOMP_RET_ALLOW.
+        for &syscall_nr in BLOCKED_SYSCALLS {
+            prog.push(SockFilter::jump(BPF_JMP | BPF_JEQ | BPF_K, syscall_nr, 0, 1));
+            prog.push(SockFilter::stmt(BPF_RET | BPF_K, SECCOMP_RET_ERRNO | EPERM));
+        }
+        prog.push(SockFilter::stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+
+        prog
+    }
+
+    pub fn install_network_seccomp() -> Result<(), WormError> {
+        // Required so this filter can be installed without CAP_SYS_ADMIN.
+        if unsafe { prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+            return Err(WormError::SecurityViolation(format!(
+                "prctl(PR_SET_NO_NEW_PRIVS) failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+
+        let filter = build_filter();
+        let fprog = SockFprog {
+            len: filter.len() as u16,
+            filter: filter.as_ptr(),
+        };
+
+        // SAFETY: `fprog` points at `filter`, which outlives this call.
+        let ret = unsafe {
+            prctl(
+                PR_SET_SECCOMP,
+                SECCOMP_MODE_FILTER as c_ulong,
+                &fprog as *const SockFprog as c_ulong,
+                0,
+                0,
+            )
+        };
+        if ret != 0 {
+            return Err(WormError::SecurityViolation(format!(
+                "prctl(PR_SET_SECCOMP) failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn build_filter_falls_through_on_mismatch_to_the_next_check() {
+            let prog = build_filter();
+            // 4 header instructions (arch load, arch jeq, kill, nr load),
+            // then 2 instructions per blocked syscall, then a trailing allow.
+            assert_eq!(prog.len(), 4 + BLOCKED_SYSCALLS.len() * 2 + 1);
+
+            for (i, &syscall_nr) in BLOCKED_SYSCALLS.iter().enumerate() {
+                let jump = &prog[4 + i * 2];
+                assert_eq!(jump.code, BPF_JMP | BPF_JEQ | BPF_K);
+                assert_eq!(jump.k, syscall_nr);
+                assert_eq!(jump.jt, 0, "a match should fall through to the very next instruction (the errno return)");
+                assert_eq!(
+                    jump.jf, 1,
+                    "a miss should skip exactly the errno return and land on the next check's jump"
+                );
+
+                let ret_errno = &prog[4 + i * 2 + 1];
+                assert_eq!(ret_errno.code, BPF_RET | BPF_K);
+                assert_eq!(ret_errno.k, SECCOMP_RET_ERRNO | EPERM);
+            }
+
+            let last = prog.last().unwrap();
+            assert_eq!(last.code, BPF_RET | BPF_K);
+            assert_eq!(last.k, SECCOMP_RET_ALLOW);
+        }
+    }
+}
+
+/// Installs a seccomp-BPF filter that makes socket-related syscalls
+/// (`socket`, `connect`, `bind`, `listen`, `accept`, `accept4`, `sendto`,
+/// `recvfrom`, `sendmsg`, `recvmsg`, `socketpair`) return `EPERM`.
+///
+/// This backs up the compile-time removal of `std::net` and the
+/// command/URL filtering in [`crate::process`] with a kernel-enforced
+/// guarantee: it holds even against `unsafe` FFI or a network tool
+/// invoked under a different name.
+///
+/// # Important
+///
+/// Seccomp filters are inherited across `fork`/`exec` and, once
+/// installed, cannot be removed for the lifetime of the process. Call
+/// this once, early in `main`, before spawning any threads - a filter
+/// installed on one thread does not apply to threads already running,
+/// and installing different filters per thread is rarely what you want.
+///
+/// On non-Linux targets this is a no-op that always returns `Ok(())`,
+/// since seccomp is a Linux-only kernel feature.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// worm_std::sandbox::install_network_seccomp().expect("seccomp install failed");
+/// worm_std::print_banner();
+/// // ... rest of the program, before any threads are spawned ...
+/// ```
+#[cfg(target_os = "linux")]
+pub fn install_network_seccomp() -> Result<(), WormError> {
+    linux::install_network_seccomp()
+}
+
+/// No-op stub: seccomp is a Linux-only kernel feature.
+#[cfg(not(target_os = "linux"))]
+pub fn install_network_seccomp() -> Result<(), WormError> {
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! URL/Host Detection
+//!
+//! `process::Command` used to flag network access attempts with a plain
+//! substring search for a handful of `scheme://` prefixes. That misses
+//! bare IP literals (`1.2.3.4`), `@`-obfuscated userinfo
+//! (`http://trusted.com@evil.com`), percent-encoded hosts, and
+//! schemeless `host:port` pairs, and it can't distinguish a legitimate
+//! internal host from an exfiltration attempt.
+//!
+//! This module parses candidate tokens into a normalized
+//! scheme/host/port triple so callers can check the *host* against a
+//! policy allowlist/denylist instead of matching raw text.
+
+/// A parsed URL or bare authority (`host[:port]`), normalized so the
+/// same host can't be represented two different ways.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedUrl {
+    pub scheme: Option<String>,
+    pub host: String,
+    pub port: Option<u16>,
+}
+
+impl ParsedUrl {
+    /// The host as it should be compared against a policy
+    /// allowlist/denylist: lowercased, percent-decoded, brackets
+    /// stripped from IPv6 literals.
+    pub fn normalized_host(&self) -> &str {
+        &self.host
+    }
+}
+
+/// Decodes `%XX` percent-escapes. Invalid escapes are passed through
+/// unchanged rather than rejected, matching the permissive style of the
+/// rest of this crate's string handling.
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+            if let Some(byte) = hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                out.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn looks_like_ipv4(host: &str) -> bool {
+    let parts: Vec<&str> = host.split('.').collect();
+    parts.len() == 4 && parts.iter().all(|p| !p.is_empty() && p.parse::<u8>().is_ok())
+}
+
+/// Splits `authority` (whatever came after `scheme://`, or a schemeless
+/// token) into `host` and an optional `port`, dropping a leading
+/// `user[:password]@` and a trailing `/path?query#fragment`.
+fn parse_authority(authority: &str) -> Option<(String, Option<u16>)> {
+    // Drop everything after the authority: path, query, fragment.
+    let authority = authority
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or(authority);
+
+    // Drop userinfo - `user:pass@host` - keeping only what's after the
+    // last `@`, since that's what the connection actually goes to.
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    if authority.is_empty() {
+        return None;
+    }
+
+    // IPv6 literal: `[::1]` or `[::1]:8080`.
+    if let Some(rest) = authority.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        let port = match rest.strip_prefix(':') {
+            Some(p) => Some(p.parse().ok()?),
+            None if rest.is_empty() => None,
+            None => return None,
+        };
+        return Some((percent_decode(host).to_lowercase(), port));
+    }
+
+    match authority.rsplit_once(':') {
+        Some((host, port)) if !host.is_empty() && port.chars().all(|c| c.is_ascii_digit()) && !port.is_empty() => {
+            Some((percent_decode(host).to_lowercase(), port.parse().ok()))
+        }
+        _ => Some((percent_decode(authority).to_lowercase(), None)),
+    }
+}
+
+/// Tries to parse `token` as a URL or bare network authority.
+///
+/// To avoid flagging ordinary text as a network address, a schemeless
+/// token (no `scheme://` prefix) is only treated as a candidate when it
+/// is an IPv4/IPv6 literal, contains userinfo (`user@host`), or pairs a
+/// domain-shaped host (one containing a `.`) with a numeric port - the
+/// shapes used to obfuscate a network
+/// destination as plain text.
+pub fn parse_candidate(token: &str) -> Option<ParsedUrl> {
+    if let Some((scheme, rest)) = token.split_once("://") {
+        let (host, port) = parse_authority(rest)?;
+        if host.is_empty() {
+            return None;
+        }
+        return Some(ParsedUrl {
+            scheme: Some(scheme.to_lowercase()),
+            host,
+            port,
+        });
+    }
+
+    let has_userinfo = token.contains('@');
+    let (host, port) = parse_authority(token)?;
+    let is_ip_literal = looks_like_ipv4(&host) || token.starts_with('[');
+    // A bare `host:port` is only treated as a candidate when the host
+    // looks domain-shaped (contains a `.`) - otherwise ordinary text
+    // like `ratio:2` would be flagged on every numeric suffix.
+    let has_domain_port = port.is_some() && host.contains('.');
+
+    if has_userinfo || is_ip_literal || has_domain_port {
+        Some(ParsedUrl {
+            scheme: None,
+            host,
+            port,
+        })
+    } else {
+        None
+    }
+}
+
+/// Scans whitespace-separated tokens in `s` for URL/authority
+/// candidates, in order.
+pub fn scan(s: &str) -> impl Iterator<Item = ParsedUrl> + '_ {
+    s.split_whitespace().filter_map(parse_candidate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_scheme_host_port() {
+        let url = parse_candidate("https://evil.com:8443/exfiltrate").unwrap();
+        assert_eq!(url.scheme.as_deref(), Some("https"));
+        assert_eq!(url.host, "evil.com");
+        assert_eq!(url.port, Some(8443));
+    }
+
+    #[test]
+    fn parses_bare_ipv4_with_port() {
+        let url = parse_candidate("echo-1.2.3.4:9000-not-a-url");
+        // Not an IPv4 literal on its own (has surrounding text), so this
+        // should not match.
+        assert!(url.is_none());
+
+        let url = parse_candidate("1.2.3.4:9000").unwrap();
+        assert_eq!(url.host, "1.2.3.4");
+        assert_eq!(url.port, Some(9000));
+    }
+
+    #[test]
+    fn parses_userinfo_obfuscation() {
+        let url = parse_candidate("http://trusted.example.com@evil.com/path").unwrap();
+        assert_eq!(url.host, "evil.com");
+    }
+
+    #[test]
+    fn parses_percent_encoded_host() {
+        let url = parse_candidate("http://%65vil.com").unwrap();
+        assert_eq!(url.host, "evil.com");
+    }
+
+    #[test]
+    fn parses_ipv6_literal() {
+        let url = parse_candidate("http://[::1]:8080/").unwrap();
+        assert_eq!(url.host, "::1");
+        assert_eq!(url.port, Some(8080));
+    }
+
+    #[test]
+    fn ignores_ordinary_text() {
+        assert!(parse_candidate("hello").is_none());
+        assert!(parse_candidate("file.txt").is_none());
+        assert!(parse_candidate("ratio:2").is_none());
+    }
+
+    #[test]
+    fn scan_finds_url_among_other_args() {
+        let found: Vec<_> = scan("--verbose http://127.0.0.1:8080/hook --quiet").collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].host, "127.0.0.1");
+    }
+}